@@ -3,19 +3,22 @@ use rustc::lint::{Context, LintPass, LintArray, Lint};
 use syntax::codemap::Span;
 use syntax::visit::{Visitor, FnKind, walk_ty};
 use utils::{in_external_macro, span_lint};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
 
 declare_lint!(pub NEEDLESS_LIFETIMES, Warn,
               "using explicit lifetimes for references in function arguments when elision rules \
                would allow omitting them");
 
+declare_lint!(pub UNUSED_LIFETIMES, Warn,
+              "unused lifetimes in function definitions");
+
 #[derive(Copy,Clone)]
 pub struct LifetimePass;
 
 impl LintPass for LifetimePass {
     fn get_lints(&self) -> LintArray {
-        lint_array!(NEEDLESS_LIFETIMES)
+        lint_array!(NEEDLESS_LIFETIMES, UNUSED_LIFETIMES)
     }
 
     fn check_fn(&mut self, cx: &Context, kind: FnKind, decl: &FnDecl,
@@ -23,10 +26,16 @@ impl LintPass for LifetimePass {
         if in_external_macro(cx, span) {
             return;
         }
-        if could_use_elision(kind, decl) {
+        let generics = match kind {
+            FnKind::FkItemFn(_, generics, _, _, _) => generics,
+            FnKind::FkMethod(_, sig, _) => &sig.generics,
+            FnKind::FkFnBlock(..) => return,
+        };
+        if could_use_elision(kind, decl, generics) {
             span_lint(cx, NEEDLESS_LIFETIMES, span,
                       "explicit lifetimes given in parameter types where they could be elided");
         }
+        report_extra_lifetimes(cx, decl, generics, span);
     }
 }
 
@@ -39,7 +48,7 @@ enum RefLt {
 }
 use self::RefLt::*;
 
-fn could_use_elision(kind: FnKind, func: &FnDecl) -> bool {
+fn could_use_elision(kind: FnKind, func: &FnDecl, generics: &Generics) -> bool {
     // There are two scenarios where elision works:
     // * no output references, all input references have different LT
     // * output references, exactly one input reference with same LT
@@ -69,6 +78,20 @@ fn could_use_elision(kind: FnKind, func: &FnDecl) -> bool {
     let input_lts = input_visitor.into_vec();
     let output_lts = output_visitor.into_vec();
 
+    // if a named lifetime we're about to flag also shows up in a bound or where-clause
+    // (`T: 'a`, `'a: 'b`, `T: Trait<'a>`, ...) it's load-bearing, not needless
+    if !generics.lifetimes.is_empty() {
+        let bound_lts = collect_bound_lifetimes(generics);
+        if input_lts.iter().chain(output_lts.iter()).any(|lt| {
+            match *lt {
+                Named(n) => bound_lts.contains(&n),
+                _ => false,
+            }
+        }) {
+            return false;
+        }
+    }
+
     // no input lifetimes? easy case!
     if input_lts.is_empty() {
         return false;
@@ -107,6 +130,203 @@ fn unique_lifetimes(lts: &Vec<RefLt>) -> usize {
     lts.iter().collect::<HashSet<_>>().len()
 }
 
+/// The last segment of a path, which is where any lifetime/type arguments live, e.g. the
+/// `Baz<'a>` in `foo::Bar::Baz<'a>`.
+fn last_path_segment(path: &Path) -> Option<&PathSegment> {
+    path.segments.last()
+}
+
+fn named_lifetime(lt: &Lifetime) -> Option<Name> {
+    if lt.name.as_str() == "'static" {
+        None
+    } else {
+        Some(lt.name)
+    }
+}
+
+/// Collects every named lifetime that appears in a function's generic parameter bounds and
+/// `where` clause: both sides of an outlives relation (`'a` and `'b` in `'a: 'b`), a
+/// type-param lifetime bound (`'a` in `T: 'a`) or a trait's lifetime argument (`T: Trait<'a>`).
+fn collect_bound_lifetimes(generics: &Generics) -> HashSet<Name> {
+    let mut bound_lts = HashSet::new();
+
+    for def in &generics.lifetimes {
+        if !def.bounds.is_empty() {
+            bound_lts.extend(named_lifetime(&def.lifetime));
+        }
+        for bound in &def.bounds {
+            bound_lts.extend(named_lifetime(bound));
+        }
+    }
+
+    for pred in &generics.where_clause.predicates {
+        match *pred {
+            WherePredicate::RegionPredicate(ref region) => {
+                bound_lts.extend(named_lifetime(&region.lifetime));
+                for bound in &region.bounds {
+                    bound_lts.extend(named_lifetime(bound));
+                }
+            }
+            WherePredicate::BoundPredicate(ref bound_pred) => {
+                for bound in &bound_pred.bounds {
+                    for_each_ty_param_bound_lifetime(bound, &mut |lt| {
+                        bound_lts.extend(named_lifetime(lt));
+                    });
+                }
+            }
+            WherePredicate::EqPredicate(_) => { }
+        }
+    }
+
+    for ty_param in &generics.ty_params {
+        for bound in &ty_param.bounds {
+            for_each_ty_param_bound_lifetime(bound, &mut |lt| {
+                bound_lts.extend(named_lifetime(lt));
+            });
+        }
+    }
+
+    bound_lts
+}
+
+/// Calls `f` with every named lifetime appearing directly on a single type-param bound:
+/// the lifetime itself for `'a`, or the lifetime arguments of the trait for `Trait<'a>`.
+/// Shared between the "is this load-bearing" pass above and the unused-lifetime pass below,
+/// which both need to walk the same `T: 'a` / `T: Trait<'a>` bound shapes.
+fn for_each_ty_param_bound_lifetime<F: FnMut(&Lifetime)>(bound: &TyParamBound, f: &mut F) {
+    match *bound {
+        TyParamBound::RegionTyParamBound(ref lt) => {
+            f(lt);
+        }
+        TyParamBound::TraitTyParamBound(ref poly_trait_ref, _) => {
+            if let Some(seg) = last_path_segment(&poly_trait_ref.trait_ref.path) {
+                if let PathParameters::AngleBracketedParameters(ref params) = seg.parameters {
+                    for lt in &params.lifetimes {
+                        f(lt);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Calls `f` with every named lifetime embedded in a type via a path's generic arguments
+/// (`Cow<'a, str>`) or a trait object's lifetime bound (`Box<Dummy + 'a>`), the cases the
+/// default AST walk doesn't otherwise surface. Shared by `RefVisitor` (elision analysis)
+/// and `LifetimeChecker` (unused-lifetime analysis) so both treat these the same way.
+fn for_each_embedded_ty_lifetime<F: FnMut(&Lifetime)>(ty: &Ty, f: &mut F) {
+    match ty.node {
+        TyPath(_, ref path) => {
+            if let Some(seg) = last_path_segment(path) {
+                if let PathParameters::AngleBracketedParameters(ref params) = seg.parameters {
+                    for lt in &params.lifetimes {
+                        f(lt);
+                    }
+                }
+            }
+        }
+        TyObjectSum(_, ref bounds) => {
+            for bound in bounds {
+                if let TyParamBound::RegionTyParamBound(ref lt) = *bound {
+                    f(lt);
+                }
+            }
+        }
+        _ => { }
+    }
+}
+
+/// Checks a function's generics for declared lifetimes that are never used in its argument
+/// types, return type, bounds or `where` clause, and lints each one found.
+fn report_extra_lifetimes(cx: &Context, decl: &FnDecl, generics: &Generics, span: Span) {
+    if generics.lifetimes.is_empty() {
+        return;
+    }
+
+    // `'static` can never be "unused", so it is never tracked here
+    let declared: HashMap<Name, Span> = generics.lifetimes.iter()
+        .filter(|def| def.lifetime.name.as_str() != "'static")
+        .map(|def| (def.lifetime.name, def.lifetime.span))
+        .collect();
+
+    if declared.is_empty() {
+        return;
+    }
+
+    let mut checker = LifetimeChecker(declared);
+
+    for arg in &decl.inputs {
+        walk_ty(&mut checker, &*arg.ty);
+    }
+    if let Return(ref ty) = decl.output {
+        walk_ty(&mut checker, ty);
+    }
+    for def in &generics.lifetimes {
+        if !def.bounds.is_empty() {
+            // `'a: 'b` constrains both sides, so both count as used, same as the
+            // `where 'a: 'b` form below
+            checker.visit_lifetime_ref(&def.lifetime);
+        }
+        for bound in &def.bounds {
+            checker.visit_lifetime_ref(bound);
+        }
+    }
+    for pred in &generics.where_clause.predicates {
+        match *pred {
+            WherePredicate::RegionPredicate(ref region) => {
+                checker.visit_lifetime_ref(&region.lifetime);
+                for bound in &region.bounds {
+                    checker.visit_lifetime_ref(bound);
+                }
+            }
+            WherePredicate::BoundPredicate(ref bound_pred) => {
+                walk_ty(&mut checker, &bound_pred.bounded_ty);
+                for bound in &bound_pred.bounds {
+                    for_each_ty_param_bound_lifetime(bound, &mut |lt| checker.visit_lifetime_ref(lt));
+                }
+            }
+            WherePredicate::EqPredicate(ref eq) => {
+                walk_ty(&mut checker, &eq.ty);
+            }
+        }
+    }
+    for ty_param in &generics.ty_params {
+        for bound in &ty_param.bounds {
+            for_each_ty_param_bound_lifetime(bound, &mut |lt| checker.visit_lifetime_ref(lt));
+        }
+    }
+
+    for (_, span) in checker.0 {
+        span_lint(cx, UNUSED_LIFETIMES, span,
+                  "this lifetime isn't used in the function definition");
+    }
+}
+
+/// A visitor used to find unused declared lifetimes: it starts out holding every lifetime
+/// declared on a function's generics, and removes each one as soon as a use is found.
+/// Whatever remains once the function's signature, bounds and `where` clause have been
+/// walked was never used.
+struct LifetimeChecker(HashMap<Name, Span>);
+
+impl<'v> Visitor<'v> for LifetimeChecker {
+    fn visit_lifetime_ref(&mut self, lifetime: &'v Lifetime) {
+        self.0.remove(&lifetime.name);
+    }
+
+    fn visit_opt_lifetime_ref(&mut self, _: Span, lifetime: &'v Option<Lifetime>) {
+        if let &Some(ref lt) = lifetime {
+            self.visit_lifetime_ref(lt);
+        }
+    }
+
+    // a lifetime embedded in a path's generic arguments or a trait object's bound counts
+    // as used too, same as for elision analysis below
+    fn visit_ty(&mut self, ty: &'v Ty) {
+        for_each_embedded_ty_lifetime(ty, &mut |lt| self.visit_lifetime_ref(lt));
+        walk_ty(self, ty);
+    }
+}
+
 /// A visitor usable for syntax::visit::walk_ty().
 struct RefVisitor(Vec<RefLt>);
 
@@ -141,4 +361,11 @@ impl<'v> Visitor<'v> for RefVisitor {
 
     // for lifetime bounds; the default impl calls visit_lifetime_ref
     fn visit_lifetime_bound(&mut self, _: &'v Lifetime) { }
-}
\ No newline at end of file
+
+    // for lifetimes hidden inside generic type arguments (`Cow<'a, str>`) and trait
+    // objects (`Box<Trait + 'a>`), which the default walk doesn't otherwise surface
+    fn visit_ty(&mut self, ty: &'v Ty) {
+        for_each_embedded_ty_lifetime(ty, &mut |lt| self.record(&Some(*lt)));
+        walk_ty(self, ty);
+    }
+}
@@ -0,0 +1,41 @@
+#![feature(plugin)]
+#![plugin(clippy)]
+#![deny(needless_lifetimes, unused_lifetimes)]
+#![allow(dead_code, unused_variables)]
+
+use std::borrow::Cow;
+
+// needless_lifetimes: plain elidable cases still get flagged
+fn distinct_lifetimes<'a, 'b>(x: &'a u8, y: &'b u8, z: u8) {} //~ERROR explicit lifetimes
+fn in_and_out<'a>(x: &'a u8) -> &'a u8 { x } //~ERROR explicit lifetimes
+
+// needless_lifetimes: a lifetime that's load-bearing in a bound or where-clause must not
+// be flagged, even though it looks elidable on the reference alone
+fn bound_on_type_param<'a, T: 'a>(x: &'a T) -> &'a T { x }
+fn bound_in_where_clause<'a, T>(x: &'a T) -> &'a T where T: 'a { x }
+fn bound_via_trait<'a, T: Trait<'a>>(x: &'a u8, _: T) -> &'a u8 { x }
+
+// needless_lifetimes: lifetimes embedded in generic type arguments or trait objects are
+// still elidable when nothing else pins them down
+fn cow_elidable<'a>(x: Cow<'a, str>) -> Cow<'a, str> { x } //~ERROR explicit lifetimes
+fn trait_object_elidable<'a>(x: Box<Dummy + 'a>) {} //~ERROR explicit lifetimes
+
+trait Trait<'a> {}
+trait Dummy {}
+
+// unused_lifetimes: a declared lifetime that never appears anywhere is flagged
+fn unused<'a>(x: u8) {} //~ERROR this lifetime isn't used
+
+// unused_lifetimes: used only via an inline type-param bound, not unused
+fn used_via_bound<'a, T: 'a>(x: T) {}
+
+// unused_lifetimes: used only via a where-clause bound, not unused
+fn used_via_where<'a, T>(x: T) where T: 'a {}
+
+// unused_lifetimes: in `'b: 'a`, 'b never appears in the signature itself, only as the
+// constrained side of the outlives relation -- that must count as a use too, whether the
+// relation is written inline on the declaration or in a where-clause
+fn used_via_outlives_inline<'a, 'b: 'a>(x: &'a u8) {}
+fn used_via_outlives_where<'a, 'b>(x: &'a u8) where 'b: 'a {}
+
+fn main() {}